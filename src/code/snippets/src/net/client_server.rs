@@ -1,8 +1,10 @@
-use fyrox::fxhash::FxHashMap;
+use fyrox::fxhash::{FxHashMap, FxHashSet};
 use fyrox::scene::node::Node;
 use fyrox::{
+    asset::ResourceKind,
     core::{
-        algebra::{UnitQuaternion, Vector3},
+        algebra::{Quaternion, UnitQuaternion, Vector3},
+        futures::executor::block_on,
         info,
         net::{NetListener, NetStream},
         pool::Handle,
@@ -12,10 +14,12 @@ use fyrox::{
     },
     graph::SceneGraph,
     plugin::{Plugin, PluginContext},
+    resource::model::Model,
     scene::{base::SceneNodeId, Scene},
 };
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::VecDeque,
     fmt::{Debug, Formatter},
     path::Path,
     path::PathBuf,
@@ -23,19 +27,293 @@ use std::{
 
 // ANCHOR: messages
 // Server messages are meant to be sent to clients.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum ServerMessage {
     LoadLevel { path: PathBuf },
-    Sync { entity_states: Vec<NodeState> },
+    // A node was created at runtime (a projectile, a pickup, a dynamically spawned player) and
+    // does not yet exist in the client's scene graph. `initial` lets the client place it correctly
+    // before the next `Sync` arrives.
+    Spawn {
+        node: SceneNodeId,
+        prefab: PathBuf,
+        initial: NodeState,
+    },
+    // A previously spawned node no longer exists on the server and should be removed on the
+    // client too.
+    Despawn {
+        node: SceneNodeId,
+    },
+    // `node` has left a connection's area of interest: unlike `Despawn`, the node still exists on
+    // the server, so the client must only stop interpolating it, not remove it - it may come back
+    // into interest later and resume from wherever a future `Sync` places it.
+    Forget {
+        node: SceneNodeId,
+    },
+    Sync {
+        entity_states: Vec<NodeState>,
+        // The most recent input sequence number this client's inputs have been applied up to,
+        // used by the client to reconcile its predicted state.
+        last_processed_input: u32,
+        // Identifies this snapshot so the client can `Ack` it back.
+        snapshot_id: u32,
+        // The snapshot `entity_states` was diffed against, or `None` if this is a full snapshot.
+        baseline_id: Option<u32>,
+    },
+    // Same as `Sync`, but with `entity_states` packed via `CompactNodeState` - selected instead of
+    // `Sync` when per-entity bandwidth matters more than the extra quantization/reconstruction
+    // work. Both ends quantize against `PositionQuantization::DEFAULT`.
+    CompactSync {
+        entity_states: Vec<CompactNodeState>,
+        last_processed_input: u32,
+        snapshot_id: u32,
+        baseline_id: Option<u32>,
+    },
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 // Client messages are meant to be sent to a server.
 pub enum ClientMessage {
-    PlayerInput { left: bool, right: bool },
+    PlayerInput {
+        left: bool,
+        right: bool,
+        // Monotonically increasing sequence number, used by the server to order inputs and by
+        // the client to know which of its predicted inputs have already been applied.
+        seq: u32,
+    },
+    // Echoes back the id of the most recent `Sync` the client has fully applied, so the server
+    // knows which snapshot it can safely diff future ones against.
+    Ack { snapshot_id: u32 },
 }
 // ANCHOR_END: messages
 
+// ANCHOR: channels
+// Logical channels multiplexed over a single `NetStream`, each with its own delivery guarantee -
+// mirroring how established game-networking stacks split traffic instead of forcing everything
+// through one lockstep-reliable pipe.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChannelId {
+    // Transform sync: only the newest state matters, so stale packets can just be dropped.
+    Sync,
+    // Level loads, spawn/despawn events and inputs: must arrive exactly once, in order.
+    Control,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeliveryMode {
+    ReliableOrdered,
+    ReliableUnordered,
+    Unreliable,
+}
+
+impl ChannelId {
+    pub fn delivery_mode(self) -> DeliveryMode {
+        match self {
+            ChannelId::Sync => DeliveryMode::Unreliable,
+            ChannelId::Control => DeliveryMode::ReliableOrdered,
+        }
+    }
+}
+
+// Wire envelope every message travels in: a per-channel sequence number, and an ack of the
+// peer's packets on the same channel, piggy-backed so acks need no message of their own. `ack` is
+// `None` until the peer has received anything on this channel - distinct from `Some(0)`, so the
+// very first packet on a channel (seq 0) is never mistaken for already acked.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Packet<M> {
+    channel: ChannelId,
+    seq: u32,
+    ack: Option<u32>,
+    // Bitfield of the 32 sequence numbers preceding `ack`.
+    ack_bits: u32,
+    payload: M,
+}
+
+struct OutgoingChannel<M> {
+    next_seq: u32,
+    // Packets sent but not yet acked by the peer - retransmitted until they are.
+    unacked: std::collections::BTreeMap<u32, M>,
+}
+
+impl<M> Default for OutgoingChannel<M> {
+    fn default() -> Self {
+        Self {
+            next_seq: 0,
+            unacked: Default::default(),
+        }
+    }
+}
+
+struct IncomingChannel<M> {
+    highest_seen: Option<u32>,
+    // Bitfield of the 32 sequence numbers preceding `highest_seen`, acked back to the peer.
+    seen_bits: u32,
+    // Next sequence number an ordered channel is waiting on to release anything.
+    next_expected: u32,
+    // Ordered arrivals that are ahead of `next_expected`, waiting on the gap before them to fill.
+    pending: std::collections::BTreeMap<u32, M>,
+}
+
+impl<M> Default for IncomingChannel<M> {
+    fn default() -> Self {
+        Self {
+            highest_seen: None,
+            seen_bits: 0,
+            next_expected: 0,
+            pending: Default::default(),
+        }
+    }
+}
+
+impl<M> IncomingChannel<M> {
+    fn ack_fields(&self) -> (Option<u32>, u32) {
+        (self.highest_seen, self.seen_bits)
+    }
+
+    // Records `seq` as seen and reports whether it had already been seen before (i.e. it is a
+    // duplicate or older than our tracking window, and should be dropped).
+    fn mark_seen(&mut self, seq: u32) -> bool {
+        let Some(highest) = self.highest_seen else {
+            self.highest_seen = Some(seq);
+            return false;
+        };
+        if seq > highest {
+            let shift = seq - highest;
+            self.seen_bits = if shift >= 32 {
+                0
+            } else {
+                (self.seen_bits << shift) | (1 << (shift - 1))
+            };
+            self.highest_seen = Some(seq);
+            false
+        } else {
+            let shift = highest - seq;
+            if shift == 0 || shift > 32 {
+                true
+            } else {
+                let bit = 1 << (shift - 1);
+                let already_seen = self.seen_bits & bit != 0;
+                self.seen_bits |= bit;
+                already_seen
+            }
+        }
+    }
+}
+
+// Per-direction channel state: `Out` is the message type this side sends, `In` the type it
+// receives.
+pub struct Channels<Out, In> {
+    outgoing: FxHashMap<ChannelId, OutgoingChannel<Out>>,
+    incoming: FxHashMap<ChannelId, IncomingChannel<In>>,
+}
+
+impl<Out, In> Default for Channels<Out, In> {
+    fn default() -> Self {
+        Self {
+            outgoing: Default::default(),
+            incoming: Default::default(),
+        }
+    }
+}
+
+impl<Out, In> Channels<Out, In>
+where
+    Out: Serialize + Clone,
+    In: for<'de> Deserialize<'de>,
+{
+    // Sends `message` on `channel`, piggy-backing an ack of everything received on it so far.
+    // Reliable channels keep a copy around until the peer acks it.
+    pub fn send(&mut self, stream: &mut NetStream, channel: ChannelId, message: Out) {
+        let out = self.outgoing.entry(channel).or_default();
+        let seq = out.next_seq;
+        out.next_seq += 1;
+        if channel.delivery_mode() != DeliveryMode::Unreliable {
+            out.unacked.insert(seq, message.clone());
+        }
+        let (ack, ack_bits) = self.incoming.entry(channel).or_default().ack_fields();
+        stream
+            .send_message(&Packet {
+                channel,
+                seq,
+                ack,
+                ack_bits,
+                payload: message,
+            })
+            .unwrap();
+    }
+
+    // Retransmits every reliable packet that has not been acked yet. Call this periodically
+    // (e.g. once per tick) alongside `send`.
+    pub fn resend_unacked(&mut self, stream: &mut NetStream) {
+        for (&channel, out) in self.outgoing.iter() {
+            if channel.delivery_mode() == DeliveryMode::Unreliable {
+                continue;
+            }
+            let (ack, ack_bits) = self.incoming.entry(channel).or_default().ack_fields();
+            for (&seq, payload) in &out.unacked {
+                stream
+                    .send_message(&Packet {
+                        channel,
+                        seq,
+                        ack,
+                        ack_bits,
+                        payload: payload.clone(),
+                    })
+                    .unwrap();
+            }
+        }
+    }
+
+    // Reads every packet currently buffered on the stream, acks the peer's packets piggy-backed
+    // on them, and delivers payloads in the order each channel's delivery mode promises.
+    pub fn receive(&mut self, stream: &mut NetStream, mut on_message: impl FnMut(ChannelId, In)) {
+        let mut arrived = Vec::new();
+        stream.process_input::<Packet<In>>(|packet| arrived.push(packet));
+
+        for packet in arrived {
+            // The peer is acking our packets - drop whatever it already has. `ack: None` means it
+            // has not received anything on this channel yet, not that it acked seq 0.
+            if let (Some(out), Some(ack)) = (self.outgoing.get_mut(&packet.channel), packet.ack) {
+                out.unacked.remove(&ack);
+                for bit in 0u32..32 {
+                    if packet.ack_bits & (1 << bit) != 0 {
+                        out.unacked.remove(&ack.saturating_sub(bit + 1));
+                    }
+                }
+            }
+
+            let incoming = self.incoming.entry(packet.channel).or_default();
+            match packet.channel.delivery_mode() {
+                DeliveryMode::Unreliable => {
+                    // Anything at or behind the newest packet seen so far is stale.
+                    let stale = incoming
+                        .highest_seen
+                        .map_or(false, |highest| packet.seq <= highest);
+                    incoming.mark_seen(packet.seq);
+                    if !stale {
+                        on_message(packet.channel, packet.payload);
+                    }
+                }
+                DeliveryMode::ReliableUnordered => {
+                    if !incoming.mark_seen(packet.seq) {
+                        on_message(packet.channel, packet.payload);
+                    }
+                }
+                DeliveryMode::ReliableOrdered => {
+                    incoming.mark_seen(packet.seq);
+                    if packet.seq >= incoming.next_expected {
+                        incoming.pending.insert(packet.seq, packet.payload);
+                    }
+                    while let Some(payload) = incoming.pending.remove(&incoming.next_expected) {
+                        incoming.next_expected += 1;
+                        on_message(packet.channel, payload);
+                    }
+                }
+            }
+        }
+    }
+}
+// ANCHOR_END: channels
+
 // ANCHOR: client_server
 // Implements listen server.
 #[derive(Default, Reflect, Debug)]
@@ -56,9 +334,13 @@ impl Plugin for Game {
         if let Some(server) = self.server.as_mut() {
             server.accept_connections();
             server.read_messages();
+            server.process_inputs(self.scene, context);
+            server.resend_unacked();
         }
         if let Some(client) = self.client.as_mut() {
-            client.read_messages();
+            client.read_messages(self.scene, context);
+            client.update_interpolation(context.dt, self.scene, context);
+            client.resend_unacked();
         }
     }
     // ANCHOR_END: update_loop
@@ -80,18 +362,82 @@ impl Plugin for Game {
                 .enabled
                 .set_value_and_mark_modified(false);
         }
+
+        if let Some(client) = self.client.as_mut() {
+            client.register_existing_nodes(&context.scenes[scene]);
+        }
+        if let Some(server) = self.server.as_mut() {
+            server.seed_known_nodes(&context.scenes[scene]);
+        }
     }
     // ANCHOR_END: disable_physics
 }
 
+// A stable, `Copy` handle to an accepted connection, so code outside the net module (e.g. a UI
+// message handler) can address a specific client without holding onto the connection itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ConnectionId(u32);
+
+// A single accepted connection, together with the server-side state needed to process that
+// client's inputs in order and to acknowledge them back.
+#[derive(Reflect)]
+pub struct Connection {
+    #[reflect(hidden)]
+    id: ConnectionId,
+    #[reflect(hidden)]
+    stream: NetStream,
+    #[reflect(hidden)]
+    pending_inputs: VecDeque<(u32, ClientMessage)>,
+    #[reflect(hidden)]
+    last_processed_input: u32,
+    // The node this connection controls. Left unassigned (`Handle::NONE`) until the game assigns
+    // a player to the connection.
+    player: Handle<Node>,
+    // Full snapshots this connection has been sent, keyed by snapshot id, so the next sync can be
+    // diffed against whichever one the client actually acked.
+    #[reflect(hidden)]
+    history: FxHashMap<u32, FxHashMap<Handle<Node>, NodeState>>,
+    // The most recent snapshot id this connection has acked, if any.
+    #[reflect(hidden)]
+    acked_snapshot: Option<u32>,
+    // Nodes included in this connection's last `Sync` under area-of-interest management, so the
+    // next one can tell which have left interest and need an explicit forget notification.
+    #[reflect(hidden)]
+    interest: FxHashSet<SceneNodeId>,
+    #[reflect(hidden)]
+    channels: Channels<ServerMessage, ClientMessage>,
+}
+
+impl Connection {
+    fn new(id: ConnectionId, stream: NetStream) -> Self {
+        Self {
+            id,
+            stream,
+            pending_inputs: Default::default(),
+            last_processed_input: 0,
+            player: Handle::NONE,
+            history: Default::default(),
+            acked_snapshot: None,
+            interest: Default::default(),
+            channels: Default::default(),
+        }
+    }
+}
+
 #[derive(Reflect)]
 pub struct Server {
     #[reflect(hidden)]
     listener: NetListener,
     #[reflect(hidden)]
-    connections: Vec<NetStream>,
+    connections: Vec<Connection>,
+    #[reflect(hidden)]
+    next_snapshot_id: u32,
+    #[reflect(hidden)]
+    next_connection_id: u32,
+    // Ids of every node replicated to clients as of the last `replicate_spawns_and_despawns`
+    // call, so the next call can tell which nodes are new and which have disappeared.
     #[reflect(hidden)]
-    prev_node_states: FxHashMap<Handle<Node>, NodeState>,
+    known_nodes: FxHashSet<SceneNodeId>,
 }
 
 impl Server {
@@ -101,51 +447,549 @@ impl Server {
         Self {
             listener: NetListener::bind(Self::ADDRESS).unwrap(),
             connections: Default::default(),
-            prev_node_states: Default::default(),
+            next_snapshot_id: 0,
+            next_connection_id: 0,
+            known_nodes: Default::default(),
         }
     }
 
     pub fn accept_connections(&mut self) {
-        self.connections.extend(self.listener.accept_connections())
+        for stream in self.listener.accept_connections() {
+            let id = ConnectionId(self.next_connection_id);
+            self.next_connection_id += 1;
+            self.connections.push(Connection::new(id, stream));
+        }
+    }
+
+    // Every connection currently known to the server, addressable via `send_to`.
+    pub fn connection_ids(&self) -> impl Iterator<Item = ConnectionId> + '_ {
+        self.connections.iter().map(|connection| connection.id)
+    }
+
+    // Marks every node already present in the scene as known, so the first
+    // `replicate_spawns_and_despawns` call does not treat the scene's static contents as freshly
+    // spawned. Call this once after the scene has loaded.
+    pub fn seed_known_nodes(&mut self, scene: &Scene) {
+        self.known_nodes
+            .extend(scene.graph.pair_iter().map(|(_, node)| node.instance_id()));
     }
 
     pub fn read_messages(&mut self) {
         for connection in self.connections.iter_mut() {
-            connection
-                .process_input::<ClientMessage>(|msg| info!("Received client message: {msg:?}"));
+            let Connection {
+                stream,
+                pending_inputs,
+                acked_snapshot,
+                channels,
+                ..
+            } = connection;
+            channels.receive(stream, |_channel, msg| {
+                info!("Received client message: {msg:?}");
+                match msg {
+                    ClientMessage::PlayerInput { seq, .. } => pending_inputs.push_back((seq, msg)),
+                    ClientMessage::Ack { snapshot_id } => {
+                        if acked_snapshot.map_or(true, |acked| snapshot_id > acked) {
+                            *acked_snapshot = Some(snapshot_id);
+                        }
+                    }
+                }
+            });
+        }
+    }
+
+    // Retransmits any reliable-channel packets that have not been acked yet. Call this
+    // periodically, alongside `read_messages`.
+    pub fn resend_unacked(&mut self) {
+        for connection in self.connections.iter_mut() {
+            connection.channels.resend_unacked(&mut connection.stream);
+        }
+    }
+
+    // ANCHOR: process_inputs
+    // Applies every buffered input to its connection's player, in the order it was received, and
+    // records how far each connection has been processed so it can be acknowledged in `Sync`.
+    pub fn process_inputs(&mut self, scene: Handle<Scene>, ctx: &mut PluginContext) {
+        let scene = some_or_return!(ctx.scenes.try_get_mut(scene));
+        for connection in self.connections.iter_mut() {
+            while let Some((seq, input)) = connection.pending_inputs.pop_front() {
+                if let Some(player) = scene.graph.try_get_mut(connection.player) {
+                    apply_player_input(player, &input);
+                }
+                connection.last_processed_input = seq;
+            }
         }
     }
+    // ANCHOR_END: process_inputs
+
+    // ANCHOR: replicate_spawns_and_despawns
+    // Diffs the scene graph against `known_nodes`, emitting a `Spawn` for every node that has
+    // appeared since the last call and a `Despawn` for every one that has disappeared. Call this
+    // before `sync`/`sync_with_delta_compression` each tick so clients always learn about a node
+    // before they are sent its state.
+    pub fn replicate_spawns_and_despawns(&mut self, scene: Handle<Scene>, ctx: &mut PluginContext) {
+        let scene = some_or_return!(ctx.scenes.try_get(scene));
+
+        let current: FxHashMap<SceneNodeId, Handle<Node>> = scene
+            .graph
+            .pair_iter()
+            .map(|(handle, node)| (node.instance_id(), handle))
+            .collect();
+
+        // Procedural/embedded nodes have no prefab path for the client to instantiate from - the
+        // client would just fail to resolve them and accumulate dead entries for state that never
+        // arrives. Still mark them known below so they are not re-considered every tick, but don't
+        // announce a `Spawn` the client provably cannot act on.
+        let spawned: Vec<ServerMessage> = current
+            .iter()
+            .filter(|(node_id, _)| !self.known_nodes.contains(*node_id))
+            .filter_map(|(&node, &handle)| {
+                let instanced = &scene.graph[handle];
+                let prefab = node_prefab_path(instanced);
+                if prefab.as_os_str().is_empty() {
+                    return None;
+                }
+                Some(ServerMessage::Spawn {
+                    node,
+                    prefab,
+                    initial: NodeState {
+                        node,
+                        position: **instanced.local_transform().position(),
+                        rotation: **instanced.local_transform().rotation(),
+                    },
+                })
+            })
+            .collect();
+
+        let despawned: Vec<SceneNodeId> = self
+            .known_nodes
+            .iter()
+            .filter(|node_id| !current.contains_key(*node_id))
+            .copied()
+            .collect();
+
+        self.known_nodes = current.into_keys().collect();
+
+        for message in spawned {
+            self.send_message_to_clients(message);
+        }
+        for node in despawned {
+            self.send_message_to_clients(ServerMessage::Despawn { node });
+        }
+    }
+    // ANCHOR_END: replicate_spawns_and_despawns
 
     pub fn send_message_to_clients(&mut self, message: ServerMessage) {
+        let channel = channel_for_server_message(&message);
         for connection in self.connections.iter_mut() {
-            connection.send_message(&message).unwrap();
+            connection
+                .channels
+                .send(&mut connection.stream, channel, message.clone());
         }
     }
+
+    // Sends `message` to a single connection, looked up by the handle returned from
+    // `connection_ids`. Does nothing if no accepted connection has that id; accepted connections
+    // are never removed, so an id stays valid after its client disconnects.
+    pub fn send_to(&mut self, id: ConnectionId, message: ServerMessage) {
+        let Some(connection) = self.connections.iter_mut().find(|c| c.id == id) else {
+            return;
+        };
+        let channel = channel_for_server_message(&message);
+        connection.channels.send(&mut connection.stream, channel, message);
+    }
+}
+
+// Routes a message to the channel matching the delivery guarantee it needs: `Sync` is frequent
+// and only the newest state matters, everything else must arrive exactly once, in order.
+fn channel_for_server_message(message: &ServerMessage) -> ChannelId {
+    match message {
+        ServerMessage::Sync { .. } | ServerMessage::CompactSync { .. } => ChannelId::Sync,
+        ServerMessage::LoadLevel { .. }
+        | ServerMessage::Spawn { .. }
+        | ServerMessage::Despawn { .. }
+        | ServerMessage::Forget { .. } => ChannelId::Control,
+    }
 }
 
+// How many snapshot ids behind the newest one `Client::snapshot_history` still keeps the
+// reconstructed full state for, so a baseline referenced by a late-arriving `Sync` can still be
+// found.
+const SNAPSHOT_HISTORY_LIMIT: u32 = 64;
+
 #[derive(Reflect)]
 pub struct Client {
     #[reflect(hidden)]
     connection: NetStream,
+    #[reflect(hidden)]
+    input_seq: u32,
+    // Inputs that were applied locally but not yet acknowledged by the server.
+    #[reflect(hidden)]
+    pending_inputs: VecDeque<(u32, ClientMessage)>,
+    // The node predicted and reconciled against the server's authoritative state.
+    player: Handle<Node>,
+    // Local clock, advanced by `update_interpolation`'s `dt` every frame. Snapshots are
+    // timestamped against it so remote entities can be rendered at `clock - interpolation_delay`.
+    #[reflect(hidden)]
+    clock: f32,
+    // How far behind the local clock remote entities are rendered, trading input latency for
+    // smoothness. 100 ms is a reasonable default for typical send rates.
+    interpolation_delay: f32,
+    // Buffered snapshots for every synced node other than `player`, newest at the back.
+    #[reflect(hidden)]
+    remote_snapshots: FxHashMap<SceneNodeId, VecDeque<(f32, NodeState)>>,
+    // Full state as of the most recently processed `Sync`, reconstructed by applying its delta on
+    // top of `snapshot_history[baseline_id]` rather than on top of whatever `known_state` already
+    // held - so a dropped `Ack` (which leaves the server diffing against an older baseline than
+    // our latest) can't leave stale values behind. Consulted for reconciliation so the player is
+    // still snapped to its authoritative position on a tick where its own state happened not to
+    // change (and so was omitted from that `Sync`'s delta).
+    #[reflect(hidden)]
+    known_state: FxHashMap<SceneNodeId, NodeState>,
+    // Every snapshot's full reconstructed state, keyed by `snapshot_id`, mirroring the server's own
+    // `Connection::history` - needed because `baseline_id` can name any snapshot we have acked,
+    // not just the latest one we have processed.
+    #[reflect(hidden)]
+    snapshot_history: FxHashMap<u32, FxHashMap<SceneNodeId, NodeState>>,
+    // Resolves a replicated node's id to its handle in the local scene graph - populated from the
+    // scene on load and kept up to date as `Spawn`/`Despawn` messages arrive, so lookups during
+    // interpolation don't need to scan the whole graph every frame.
+    #[reflect(hidden)]
+    nodes: FxHashMap<SceneNodeId, Handle<Node>>,
+    #[reflect(hidden)]
+    channels: Channels<ClientMessage, ServerMessage>,
 }
 
 impl Client {
     pub fn connect(address: &str) -> Self {
         Self {
             connection: NetStream::connect(address).unwrap(),
+            input_seq: 0,
+            pending_inputs: Default::default(),
+            player: Handle::NONE,
+            clock: 0.0,
+            interpolation_delay: 0.1,
+            remote_snapshots: Default::default(),
+            known_state: Default::default(),
+            snapshot_history: Default::default(),
+            nodes: Default::default(),
+            channels: Default::default(),
         }
     }
 
-    pub fn read_messages(&mut self) {
-        self.connection
-            .process_input::<ServerMessage>(|msg| info!("Received server message: {msg:?}"));
+    // Retransmits any reliable-channel packets that have not been acked yet. Call this
+    // periodically, alongside `read_messages`.
+    pub fn resend_unacked(&mut self) {
+        self.channels.resend_unacked(&mut self.connection);
+    }
+
+    // Registers every node already present in the scene (i.e. everything loaded statically
+    // instead of replicated via `Spawn`), so interpolation can resolve them through `nodes` too.
+    // Call this once after the scene has loaded.
+    pub fn register_existing_nodes(&mut self, scene: &Scene) {
+        self.nodes.extend(
+            scene
+                .graph
+                .pair_iter()
+                .map(|(handle, node)| (node.instance_id(), handle)),
+        );
+    }
+
+    pub fn set_interpolation_delay(&mut self, delay: f32) {
+        self.interpolation_delay = delay;
+    }
+
+    // ANCHOR: send_player_input
+    // Sends the input to the server and immediately applies it locally (client-side prediction),
+    // keeping it buffered until the server acknowledges it.
+    pub fn send_player_input(
+        &mut self,
+        left: bool,
+        right: bool,
+        scene: Handle<Scene>,
+        ctx: &mut PluginContext,
+    ) {
+        self.input_seq += 1;
+        let message = ClientMessage::PlayerInput {
+            left,
+            right,
+            seq: self.input_seq,
+        };
+        self.channels
+            .send(&mut self.connection, ChannelId::Control, message.clone());
+
+        if let Some(scene) = ctx.scenes.try_get_mut(scene) {
+            if let Some(player) = scene.graph.try_get_mut(self.player) {
+                apply_player_input(player, &message);
+            }
+        }
+
+        self.pending_inputs.push_back((self.input_seq, message));
+    }
+    // ANCHOR_END: send_player_input
+
+    // ANCHOR: reconciliation
+    pub fn read_messages(&mut self, scene: Handle<Scene>, ctx: &mut PluginContext) {
+        let mut last_sync = None;
+        let nodes = &mut self.nodes;
+        let remote_snapshots = &mut self.remote_snapshots;
+        let known_state = &mut self.known_state;
+        self.channels.receive(&mut self.connection, |_channel, msg| {
+            info!("Received server message: {msg:?}");
+            match msg {
+                ServerMessage::Sync {
+                    entity_states,
+                    last_processed_input,
+                    snapshot_id,
+                    baseline_id,
+                } => {
+                    last_sync =
+                        Some((entity_states, last_processed_input, snapshot_id, baseline_id));
+                }
+                ServerMessage::CompactSync {
+                    entity_states,
+                    last_processed_input,
+                    snapshot_id,
+                    baseline_id,
+                } => {
+                    let entity_states = entity_states
+                        .iter()
+                        .map(|state| state.decode(PositionQuantization::DEFAULT))
+                        .collect();
+                    last_sync =
+                        Some((entity_states, last_processed_input, snapshot_id, baseline_id));
+                }
+                ServerMessage::Spawn {
+                    node,
+                    prefab,
+                    initial,
+                } => spawn_replicated_node(nodes, node, prefab, initial, scene, ctx),
+                ServerMessage::Despawn { node } => {
+                    despawn_replicated_node(nodes, remote_snapshots, known_state, node, scene, ctx)
+                }
+                ServerMessage::Forget { node } => {
+                    remote_snapshots.remove(&node);
+                }
+                // Handled elsewhere, by whatever drives the engine's scene loading.
+                ServerMessage::LoadLevel { .. } => {}
+            }
+        });
+
+        let Some((entity_states, last_processed_input, snapshot_id, baseline_id)) = last_sync
+        else {
+            return;
+        };
+
+        // Drop every input the server has already applied.
+        self.pending_inputs
+            .retain(|(seq, _)| *seq > last_processed_input);
+
+        // Reconstruct the full state by applying the delta on top of the specific snapshot the
+        // server diffed against - not whatever `known_state` currently holds. If our `Ack` for that
+        // baseline was lost, the server is still diffing against an older snapshot than our most
+        // recent one, and folding the delta into a rolling `known_state` would leave stale values
+        // for anything that changed since and then returned to exactly its baseline value (it
+        // would be absent from this delta too). A full snapshot has no baseline to restore.
+        match baseline_id.and_then(|id| self.snapshot_history.get(&id)) {
+            Some(baseline) => self.known_state = baseline.clone(),
+            None => self.known_state.clear(),
+        }
+        for state in &entity_states {
+            self.known_state.insert(state.node, state.clone());
+        }
+        self.snapshot_history
+            .insert(snapshot_id, self.known_state.clone());
+        // Forget snapshots old enough that the server could only still be diffing against them
+        // after an implausibly long run of dropped acks.
+        self.snapshot_history
+            .retain(|id, _| snapshot_id.saturating_sub(*id) <= SNAPSHOT_HISTORY_LIMIT);
+
+        self.channels.send(
+            &mut self.connection,
+            ChannelId::Control,
+            ClientMessage::Ack { snapshot_id },
+        );
+
+        // Buffer every snapshot (including the player's - `update_interpolation` skips it) so
+        // remote entities can be interpolated between two bracketing snapshots later.
+        let receive_time = self.clock;
+        for state in &entity_states {
+            self.remote_snapshots
+                .entry(state.node)
+                .or_default()
+                .push_back((receive_time, state.clone()));
+        }
+
+        let Some(scene) = ctx.scenes.try_get_mut(scene) else {
+            return;
+        };
+        let Some(player) = scene.graph.try_get(self.player) else {
+            return;
+        };
+        let player_id = player.instance_id();
+        // Looked up in the reconstructed state rather than `entity_states` directly - a
+        // delta-compressed sync omits the player entirely when its position has not changed
+        // since the baseline, and `entity_states` alone would have nothing to reconcile against.
+        let Some(authoritative) = self.known_state.get(&player_id) else {
+            return;
+        };
+
+        // Snap to the authoritative state, then replay every input the server has not processed
+        // yet to recompute the predicted position.
+        let position = authoritative.position;
+        let rotation = authoritative.rotation;
+        if let Some(player) = scene.graph.try_get_mut(self.player) {
+            player
+                .local_transform_mut()
+                .set_position(position)
+                .set_rotation(rotation);
+        }
+        for (_, input) in &self.pending_inputs {
+            if let Some(player) = scene.graph.try_get_mut(self.player) {
+                apply_player_input(player, input);
+            }
+        }
     }
+    // ANCHOR_END: reconciliation
 
     pub fn send_message_to_server(&mut self, message: ClientMessage) {
-        self.connection.send_message(&message).unwrap();
+        self.channels
+            .send(&mut self.connection, ChannelId::Control, message);
+    }
+
+    // ANCHOR: interpolation
+    // Renders every remote entity at `clock - interpolation_delay`, lerping/slerping between the
+    // two buffered snapshots that bracket that point in time. `player` is skipped - it is driven
+    // by prediction and reconciliation instead.
+    pub fn update_interpolation(&mut self, dt: f32, scene: Handle<Scene>, ctx: &mut PluginContext) {
+        self.clock += dt;
+        let render_time = self.clock - self.interpolation_delay;
+
+        let Some(scene) = ctx.scenes.try_get_mut(scene) else {
+            return;
+        };
+
+        for (node_id, snapshots) in self.remote_snapshots.iter_mut() {
+            // Drop snapshots that are entirely in the past - we only ever need the last one
+            // before `render_time` plus whatever comes after it.
+            while snapshots.len() > 1 && snapshots[1].0 <= render_time {
+                snapshots.pop_front();
+            }
+
+            let Some(&node) = self.nodes.get(node_id) else {
+                continue;
+            };
+            if node == self.player {
+                continue;
+            }
+
+            match snapshots.make_contiguous() {
+                [(t0, s0), (t1, s1), ..] if render_time >= *t0 => {
+                    let t = ((render_time - *t0) / (*t1 - *t0).max(f32::EPSILON)).clamp(0.0, 1.0);
+                    if let Some(node) = scene.graph.try_get_mut(node) {
+                        node.local_transform_mut()
+                            .set_position(s0.position.lerp(&s1.position, t))
+                            .set_rotation(s0.rotation.slerp(&s1.rotation, t));
+                    }
+                }
+                // No snapshot old enough (just joined) or no newer one yet (send rate hiccup) -
+                // hold at the most recent snapshot we have.
+                [.., (_, latest)] => {
+                    if let Some(node) = scene.graph.try_get_mut(node) {
+                        node.local_transform_mut()
+                            .set_position(latest.position)
+                            .set_rotation(latest.rotation);
+                    }
+                }
+                [] => {}
+            }
+        }
+    }
+    // ANCHOR_END: interpolation
+}
+
+// Instantiates `prefab`, places it at `initial`'s transform, and registers the resulting handle
+// under `node` so subsequent `Sync` updates resolve onto it.
+fn spawn_replicated_node(
+    nodes: &mut FxHashMap<SceneNodeId, Handle<Node>>,
+    node: SceneNodeId,
+    prefab: PathBuf,
+    initial: NodeState,
+    scene: Handle<Scene>,
+    ctx: &mut PluginContext,
+) {
+    // Already resolved - either a previous `Spawn` for this id, or a node that was already in the
+    // scene (registered by `register_existing_nodes`). Instantiating again would duplicate it and
+    // orphan whatever is already driving it.
+    if nodes.contains_key(&node) {
+        return;
+    }
+    let Ok(model) = block_on(ctx.resource_manager.request::<Model>(prefab)) else {
+        return;
+    };
+    let Some(scene) = ctx.scenes.try_get_mut(scene) else {
+        return;
+    };
+    let handle = model.instantiate(scene);
+    if let Some(instanced) = scene.graph.try_get_mut(handle) {
+        instanced
+            .local_transform_mut()
+            .set_position(initial.position)
+            .set_rotation(initial.rotation);
+    }
+    nodes.insert(node, handle);
+}
+
+// Removes a previously spawned node, both from the scene and from every map keyed by its id -
+// `Despawn` is final, unlike `Forget`, so nothing should be left behind to leak for the rest of
+// the session.
+fn despawn_replicated_node(
+    nodes: &mut FxHashMap<SceneNodeId, Handle<Node>>,
+    remote_snapshots: &mut FxHashMap<SceneNodeId, VecDeque<(f32, NodeState)>>,
+    known_state: &mut FxHashMap<SceneNodeId, NodeState>,
+    node: SceneNodeId,
+    scene: Handle<Scene>,
+    ctx: &mut PluginContext,
+) {
+    remote_snapshots.remove(&node);
+    known_state.remove(&node);
+    let Some(handle) = nodes.remove(&node) else {
+        return;
+    };
+    if let Some(scene) = ctx.scenes.try_get_mut(scene) {
+        scene.graph.remove_node(handle);
     }
 }
 
+// Resolves the path of the prefab a node was instantiated from, so a client receiving `Spawn` for
+// it knows what to instantiate. Procedurally created nodes with no backing resource have nothing
+// sensible to report and are expected to be spawned through some other game-specific means.
+fn node_prefab_path(node: &Node) -> PathBuf {
+    match node.resource() {
+        Some(resource) => match resource.kind() {
+            ResourceKind::External(path) => path,
+            ResourceKind::Embedded => PathBuf::new(),
+        },
+        None => PathBuf::new(),
+    }
+}
+
+// Moves the given node according to a `PlayerInput`. Used both by the server (authoritative
+// simulation) and by the client (prediction and reconciliation replay), so the two can never
+// diverge in how an input is interpreted.
+fn apply_player_input(node: &mut Node, input: &ClientMessage) {
+    let ClientMessage::PlayerInput { left, right, .. } = input else {
+        return;
+    };
+    let mut offset = Vector3::default();
+    if *left {
+        offset.x -= 1.0;
+    }
+    if *right {
+        offset.x += 1.0;
+    }
+    node.local_transform_mut().offset(offset);
+}
+
 impl Visit for Game {
     fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
         // This must be implemented only for hot-reloading support. It is up to you to maintain
@@ -171,12 +1015,47 @@ impl Game {
                 // Player's moving left.
                 left: true,
                 right: false,
+                seq: {
+                    client.input_seq += 1;
+                    client.input_seq
+                },
             })
         }
     }
 }
 // ANCHOR_END: send_test_messages
 
+// ANCHOR: ui_click_handling
+// With `ConnectionId` and `Server::send_to` in place, a UI message handler can address a
+// specific client, and the client is reachable straight off the plugin to send its own message -
+// neither was possible without restructuring the whole update loop.
+impl Game {
+    fn on_button_click(&mut self) {
+        if let Some(client) = self.client.as_mut() {
+            client.send_message_to_server(ClientMessage::PlayerInput {
+                left: false,
+                right: true,
+                seq: {
+                    client.input_seq += 1;
+                    client.input_seq
+                },
+            });
+        }
+    }
+
+    fn kick(&mut self, connection: ConnectionId) {
+        if let Some(server) = self.server.as_mut() {
+            server.send_to(
+                connection,
+                ServerMessage::LoadLevel {
+                    path: PathBuf::from("data/scenes/lobby.rgs"),
+                },
+            );
+        }
+    }
+}
+// ANCHOR_END: ui_click_handling
+
 // ANCHOR: simple_syncing
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct NodeState {
@@ -196,39 +1075,437 @@ impl Server {
                 rotation: **node.local_transform().rotation(),
             });
         }
-        self.send_message_to_clients(ServerMessage::Sync { entity_states });
+        let snapshot_id = self.next_snapshot_id;
+        self.next_snapshot_id += 1;
+        for connection in self.connections.iter_mut() {
+            connection.channels.send(
+                &mut connection.stream,
+                ChannelId::Sync,
+                ServerMessage::Sync {
+                    entity_states: entity_states.clone(),
+                    last_processed_input: connection.last_processed_input,
+                    snapshot_id,
+                    baseline_id: None,
+                },
+            );
+        }
     }
 }
 // ANCHOR_END: simple_syncing
 
+// ANCHOR: compact_node_state
+// Quantization parameters for the fixed-point position encoding below - the world is assumed to
+// fit within `[-half_extent, half_extent]` on every axis. Fewer bits or a smaller extent buy
+// smaller packets at the cost of precision; pick whatever the game's level bounds can tolerate.
+#[derive(Debug, Clone, Copy)]
+pub struct PositionQuantization {
+    pub half_extent: f32,
+    pub bits_per_axis: u32,
+}
+
+impl PositionQuantization {
+    // 10 bits per axis over +/-100 units is about 0.2 units of precision, and packs all three
+    // axes into a single `u32` - a reasonable default for small, arena-sized levels.
+    pub const DEFAULT: Self = Self {
+        half_extent: 100.0,
+        bits_per_axis: 10,
+    };
+
+    fn encode(self, position: Vector3<f32>) -> u32 {
+        let max = (1u32 << self.bits_per_axis) - 1;
+        let axis = |value: f32| -> u32 {
+            let normalized = (value.clamp(-self.half_extent, self.half_extent) + self.half_extent)
+                / (2.0 * self.half_extent);
+            (normalized * max as f32).round() as u32
+        };
+        (axis(position.x) << (2 * self.bits_per_axis))
+            | (axis(position.y) << self.bits_per_axis)
+            | axis(position.z)
+    }
+
+    fn decode(self, packed: u32) -> Vector3<f32> {
+        let max = (1u32 << self.bits_per_axis) - 1;
+        let axis = |encoded: u32| -> f32 {
+            (encoded as f32 / max as f32) * (2.0 * self.half_extent) - self.half_extent
+        };
+        let z = axis(packed & max);
+        let y = axis((packed >> self.bits_per_axis) & max);
+        let x = axis((packed >> (2 * self.bits_per_axis)) & max);
+        Vector3::new(x, y, z)
+    }
+}
+
+// In a unit quaternion, every component other than the largest-magnitude one lies within
+// `[-1/sqrt(2), 1/sqrt(2)]`, and since a quaternion and its negation encode the same rotation,
+// the sign can always be flipped to make the largest component positive. That means only the
+// other three need to be sent, quantized over that range, plus a 2-bit
+// index saying which was dropped - the dropped one is reconstructed on the other end as
+// `sqrt(1 - a^2 - b^2 - c^2)`.
+const ROTATION_COMPONENT_BITS: u32 = 10;
+const ROTATION_COMPONENT_RANGE: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+fn quantize_rotation(rotation: UnitQuaternion<f32>) -> u32 {
+    let q = rotation.quaternion();
+    let components = [q.i, q.j, q.k, q.w];
+    let (largest_index, &largest) = components
+        .iter()
+        .enumerate()
+        .max_by(|(_, a), (_, b)| a.abs().total_cmp(&b.abs()))
+        .unwrap();
+    let sign = largest.signum();
+
+    let max = (1u32 << ROTATION_COMPONENT_BITS) - 1;
+    let mut packed = largest_index as u32;
+    for (i, &component) in components.iter().enumerate() {
+        if i == largest_index {
+            continue;
+        }
+        let normalized = ((component * sign).clamp(-ROTATION_COMPONENT_RANGE, ROTATION_COMPONENT_RANGE)
+            + ROTATION_COMPONENT_RANGE)
+            / (2.0 * ROTATION_COMPONENT_RANGE);
+        packed = (packed << ROTATION_COMPONENT_BITS) | (normalized * max as f32).round() as u32;
+    }
+    packed
+}
+
+fn dequantize_rotation(packed: u32) -> UnitQuaternion<f32> {
+    let max = (1u32 << ROTATION_COMPONENT_BITS) - 1;
+    let decode = |encoded: u32| -> f32 {
+        (encoded as f32 / max as f32) * (2.0 * ROTATION_COMPONENT_RANGE) - ROTATION_COMPONENT_RANGE
+    };
+
+    let c2 = decode(packed & max);
+    let c1 = decode((packed >> ROTATION_COMPONENT_BITS) & max);
+    let c0 = decode((packed >> (2 * ROTATION_COMPONENT_BITS)) & max);
+    let largest_index = (packed >> (3 * ROTATION_COMPONENT_BITS)) & 0b11;
+
+    let largest = (1.0 - (c0 * c0 + c1 * c1 + c2 * c2)).max(0.0).sqrt();
+    let mut remaining = [c0, c1, c2].into_iter();
+    let mut components = [0.0f32; 4];
+    for (i, slot) in components.iter_mut().enumerate() {
+        *slot = if i as u32 == largest_index {
+            largest
+        } else {
+            remaining.next().unwrap()
+        };
+    }
+
+    UnitQuaternion::new_normalize(Quaternion::new(
+        components[3],
+        components[0],
+        components[1],
+        components[2],
+    ))
+}
+
+// Compact wire form of `NodeState`, selected per sync when bandwidth matters more than the extra
+// quantization/reconstruction work: packs rotation and position - 28 bytes as plain floats - into
+// two `u32`s, 8 bytes, not counting `node`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct CompactNodeState {
+    pub node: SceneNodeId,
+    rotation: u32,
+    position: u32,
+}
+
+impl CompactNodeState {
+    pub fn encode(state: &NodeState, position_quantization: PositionQuantization) -> Self {
+        Self {
+            node: state.node,
+            rotation: quantize_rotation(state.rotation),
+            position: position_quantization.encode(state.position),
+        }
+    }
+
+    pub fn decode(&self, position_quantization: PositionQuantization) -> NodeState {
+        NodeState {
+            node: self.node,
+            position: position_quantization.decode(self.position),
+            rotation: dequantize_rotation(self.rotation),
+        }
+    }
+}
+
+#[cfg(test)]
+mod compact_node_state_tests {
+    use super::*;
+
+    // The reconstructed component dropped from the quaternion is derived from the other three,
+    // so quantization error in those three compounds into it - this asserts it stays small enough
+    // to be visually indistinguishable from the original rotation.
+    const ROTATION_EPSILON: f32 = 1.0e-2;
+
+    #[test]
+    fn rotation_round_trips_within_epsilon() {
+        let rotations = [
+            UnitQuaternion::identity(),
+            UnitQuaternion::from_euler_angles(0.3, 1.2, -0.7),
+            UnitQuaternion::from_euler_angles(std::f32::consts::PI, 0.0, 0.0),
+            UnitQuaternion::from_euler_angles(0.0, std::f32::consts::FRAC_PI_2, 0.1),
+            UnitQuaternion::from_euler_angles(-1.5, -2.6, 3.0),
+        ];
+
+        for rotation in rotations {
+            let packed = quantize_rotation(rotation);
+            let reconstructed = dequantize_rotation(packed);
+            let angle_error = rotation.angle_to(&reconstructed);
+            assert!(
+                angle_error < ROTATION_EPSILON,
+                "angle error {angle_error} too large for {rotation:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn position_round_trips_within_quantization_step() {
+        let quantization = PositionQuantization::DEFAULT;
+        let position = Vector3::new(12.34, -56.7, 0.0);
+
+        let packed = quantization.encode(position);
+        let reconstructed = quantization.decode(packed);
+
+        let step = (2.0 * quantization.half_extent) / ((1u32 << quantization.bits_per_axis) - 1) as f32;
+        assert!((position - reconstructed).norm() < step * 3.0_f32.sqrt());
+    }
+}
+// ANCHOR_END: compact_node_state
+
+// ANCHOR: compact_syncing
+impl Server {
+    // Same as `sync`, but wired to actually put the quantization to use: every `NodeState` is
+    // packed into a `CompactNodeState` before being sent, cutting each entity down from 28 bytes
+    // to about 7-8. Pick this over `sync` for send rates or player counts where that difference
+    // matters; `sync_with_delta_compression`/`sync_with_interest_management` could be switched the
+    // same way if their bandwidth profile called for it.
+    pub fn sync_compact(&mut self, scene: Handle<Scene>, ctx: &mut PluginContext) {
+        let scene = some_or_return!(ctx.scenes.try_get(scene));
+        let mut entity_states = Vec::with_capacity(scene.graph.capacity() as usize);
+        for (_, node) in scene.graph.pair_iter() {
+            let state = NodeState {
+                node: node.instance_id(),
+                position: **node.local_transform().position(),
+                rotation: **node.local_transform().rotation(),
+            };
+            entity_states.push(CompactNodeState::encode(&state, PositionQuantization::DEFAULT));
+        }
+        let snapshot_id = self.next_snapshot_id;
+        self.next_snapshot_id += 1;
+        for connection in self.connections.iter_mut() {
+            connection.channels.send(
+                &mut connection.stream,
+                ChannelId::Sync,
+                ServerMessage::CompactSync {
+                    entity_states: entity_states.clone(),
+                    last_processed_input: connection.last_processed_input,
+                    snapshot_id,
+                    baseline_id: None,
+                },
+            );
+        }
+    }
+}
+// ANCHOR_END: compact_syncing
+
 // ANCHOR: syncing_with_delta_compression
 impl Server {
+    // Diffs each connection's next snapshot against the newest baseline that connection has
+    // actually acked (falling back to a full snapshot if it has not acked anything yet), so a
+    // client that missed a packet is resent everything instead of drifting out of sync forever.
     pub fn sync_with_delta_compression(&mut self, scene: Handle<Scene>, ctx: &mut PluginContext) {
         let scene = some_or_return!(ctx.scenes.try_get(scene));
-        let mut entity_states = Vec::with_capacity(scene.graph.capacity() as usize);
+        let mut current_state = FxHashMap::default();
         for (handle, node) in scene.graph.pair_iter() {
-            let current_state = NodeState {
+            current_state.insert(
+                handle,
+                NodeState {
+                    node: node.instance_id(),
+                    position: **node.local_transform().position(),
+                    rotation: **node.local_transform().rotation(),
+                },
+            );
+        }
+
+        let snapshot_id = self.next_snapshot_id;
+        self.next_snapshot_id += 1;
+
+        for connection in self.connections.iter_mut() {
+            let baseline = connection
+                .acked_snapshot
+                .and_then(|acked| connection.history.get(&acked));
+
+            let entity_states = current_state
+                .iter()
+                .filter(|(handle, state)| baseline.and_then(|b| b.get(*handle)) != Some(*state))
+                .map(|(_, state)| state.clone())
+                .collect();
+
+            let baseline_id = if baseline.is_some() {
+                connection.acked_snapshot
+            } else {
+                None
+            };
+
+            connection.channels.send(
+                &mut connection.stream,
+                ChannelId::Sync,
+                ServerMessage::Sync {
+                    entity_states,
+                    last_processed_input: connection.last_processed_input,
+                    snapshot_id,
+                    baseline_id,
+                },
+            );
+
+            connection.history.insert(snapshot_id, current_state.clone());
+            // Snapshots older than the acked baseline will never be diffed against again.
+            if let Some(acked) = connection.acked_snapshot {
+                connection.history.retain(|id, _| *id >= acked);
+            }
+        }
+    }
+}
+// ANCHOR_END: syncing_with_delta_compression
+
+// ANCHOR: area_of_interest
+// Configures which nodes a connection is sent: anything within `radius` units of its player, plus
+// anything whose id is in `always_relevant` regardless of distance (e.g. the level's objective
+// markers). `cell_size` sizes the spatial hash used to find candidates without an all-pairs
+// distance check every tick - pick something on the order of `radius` itself.
+#[derive(Debug, Clone)]
+pub struct AreaOfInterest {
+    pub radius: f32,
+    pub cell_size: f32,
+    pub always_relevant: FxHashSet<SceneNodeId>,
+}
+
+impl AreaOfInterest {
+    pub fn new(radius: f32, cell_size: f32) -> Self {
+        Self {
+            radius,
+            cell_size,
+            always_relevant: Default::default(),
+        }
+    }
+}
+
+fn spatial_hash_cell(position: Vector3<f32>, cell_size: f32) -> (i32, i32, i32) {
+    (
+        (position.x / cell_size).floor() as i32,
+        (position.y / cell_size).floor() as i32,
+        (position.z / cell_size).floor() as i32,
+    )
+}
+
+// A spatial hash over node positions, rebuilt from scratch every tick from the current snapshot,
+// so `nearby` can narrow a point query down to a handful of candidates instead of scanning every
+// node in the graph.
+struct SpatialHash<'a> {
+    cell_size: f32,
+    cells: FxHashMap<(i32, i32, i32), Vec<&'a NodeState>>,
+}
+
+impl<'a> SpatialHash<'a> {
+    fn build(states: impl Iterator<Item = &'a NodeState>, cell_size: f32) -> Self {
+        let mut cells: FxHashMap<(i32, i32, i32), Vec<&'a NodeState>> = Default::default();
+        for state in states {
+            cells
+                .entry(spatial_hash_cell(state.position, cell_size))
+                .or_default()
+                .push(state);
+        }
+        Self { cell_size, cells }
+    }
+
+    // Every state in the block of cells around `center` that covers everything within `reach` of
+    // it - a superset of what is actually within `reach`, since whole cells are taken. Callers
+    // are expected to apply the exact distance check themselves.
+    fn nearby(&self, center: Vector3<f32>, reach: f32) -> impl Iterator<Item = &'a NodeState> + '_ {
+        let (cx, cy, cz) = spatial_hash_cell(center, self.cell_size);
+        let span = (reach / self.cell_size).ceil() as i32;
+        (-span..=span)
+            .flat_map(move |dx| (-span..=span).map(move |dy| (dx, dy)))
+            .flat_map(move |(dx, dy)| (-span..=span).map(move |dz| (dx, dy, dz)))
+            .filter_map(move |(dx, dy, dz)| self.cells.get(&(cx + dx, cy + dy, cz + dz)))
+            .flatten()
+            .copied()
+    }
+}
+
+impl Server {
+    // Builds a per-connection `Sync` containing only nodes within its player's area of interest,
+    // instead of broadcasting an identical snapshot to every client. Nodes that were in a
+    // connection's interest set last tick but are not anymore are sent a `Forget` so the client
+    // stops interpolating them - the node still exists on the server, so it is not `Despawn`ed.
+    pub fn sync_with_interest_management(
+        &mut self,
+        scene: Handle<Scene>,
+        ctx: &mut PluginContext,
+        aoi: &AreaOfInterest,
+    ) {
+        let scene = some_or_return!(ctx.scenes.try_get(scene));
+
+        let mut states = Vec::with_capacity(scene.graph.capacity() as usize);
+        for (_, node) in scene.graph.pair_iter() {
+            states.push(NodeState {
                 node: node.instance_id(),
                 position: **node.local_transform().position(),
                 rotation: **node.local_transform().rotation(),
+            });
+        }
+        let hash = SpatialHash::build(states.iter(), aoi.cell_size);
+
+        let snapshot_id = self.next_snapshot_id;
+        self.next_snapshot_id += 1;
+
+        for connection in self.connections.iter_mut() {
+            let Some(player) = scene.graph.try_get(connection.player) else {
+                continue;
             };
+            let player_position = **player.local_transform().position();
+
+            let mut in_interest: FxHashSet<SceneNodeId> = hash
+                .nearby(player_position, aoi.radius)
+                .filter(|state| (state.position - player_position).norm() <= aoi.radius)
+                .map(|state| state.node)
+                .collect();
+            in_interest.extend(aoi.always_relevant.iter().copied());
 
-            // Simple delta compression.
-            let prev_state = self
-                .prev_node_states
-                .entry(handle)
-                .or_insert(current_state.clone());
+            let entity_states: Vec<NodeState> = states
+                .iter()
+                .filter(|state| in_interest.contains(&state.node))
+                .cloned()
+                .collect();
 
-            if *prev_state != current_state {
-                entity_states.push(current_state.clone());
-                *prev_state = current_state;
+            let forgotten: Vec<SceneNodeId> = connection
+                .interest
+                .iter()
+                .filter(|node| !in_interest.contains(node))
+                .copied()
+                .collect();
+
+            connection.channels.send(
+                &mut connection.stream,
+                ChannelId::Sync,
+                ServerMessage::Sync {
+                    entity_states,
+                    last_processed_input: connection.last_processed_input,
+                    snapshot_id,
+                    baseline_id: None,
+                },
+            );
+            for node in forgotten {
+                connection.channels.send(
+                    &mut connection.stream,
+                    ChannelId::Control,
+                    ServerMessage::Forget { node },
+                );
             }
-        }
 
-        self.send_message_to_clients(ServerMessage::Sync { entity_states });
+            connection.interest = in_interest;
+        }
     }
 }
-// ANCHOR_END: syncing_with_delta_compression
+// ANCHOR_END: area_of_interest
 
 impl Debug for Server {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {